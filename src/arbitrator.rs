@@ -0,0 +1,258 @@
+use crate::{gps, ip, wifi, Location};
+use anyhow::Result;
+use reqwest::Client;
+use std::time::SystemTime;
+
+/// Which source produced a [`SourceFix`], kept around so arbitration
+/// decisions and logging can refer to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocationSource {
+    Gps,
+    Wifi,
+    Ip,
+}
+
+/// A coordinate fix as reported by one source, annotated with the accuracy
+/// and the time it was observed so fixes from different sources can be
+/// compared.
+#[derive(Debug, Clone)]
+pub struct SourceFix {
+    pub lat: f64,
+    pub lon: f64,
+    pub altitude_meters: Option<f64>,
+    pub accuracy_meters: Option<f64>,
+    pub observed_at: SystemTime,
+    pub source: LocationSource,
+}
+
+/// How [`LocationArbitrator`] picks a fix when multiple sources respond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArbitrationMode {
+    /// Return the first source to succeed, in GPS > Wi-Fi > IP order. This
+    /// is the historical behavior and is the cheapest in time and requests.
+    FastFirstSuccess,
+    /// Query every source and pick the best fix using accuracy and
+    /// recency, the same way Chromium's network location provider
+    /// arbitrates between its sources.
+    BestOfAll,
+}
+
+/// A fix that is more than this many times less accurate than another is
+/// considered "significantly worse" for the purposes of preferring
+/// recency over accuracy (or vice versa) in [`select_best`].
+const SIGNIFICANTLY_LESS_ACCURATE_FACTOR: f64 = 2.0;
+
+/// Collects fixes from the available location sources and arbitrates
+/// between them according to an [`ArbitrationMode`].
+pub struct LocationArbitrator {
+    mode: ArbitrationMode,
+    max_accuracy_meters: Option<f64>,
+}
+
+impl LocationArbitrator {
+    pub fn new(mode: ArbitrationMode) -> Self {
+        LocationArbitrator {
+            mode,
+            max_accuracy_meters: None,
+        }
+    }
+
+    /// Discards fixes whose accuracy radius exceeds `max_accuracy_meters`.
+    pub fn with_max_accuracy_meters(mut self, max_accuracy_meters: f64) -> Self {
+        self.max_accuracy_meters = Some(max_accuracy_meters);
+        self
+    }
+
+    pub async fn locate(&self) -> Result<Location> {
+        match self.mode {
+            ArbitrationMode::FastFirstSuccess => self.locate_fast().await,
+            ArbitrationMode::BestOfAll => self.locate_best_of_all().await,
+        }
+    }
+
+    async fn locate_fast(&self) -> Result<Location> {
+        if let Ok((lat, lon, altitude_meters, accuracy_meters)) = gps::get_gps_location() {
+            return Ok(Location { lat, lon, altitude_meters, accuracy_meters, captured_at: SystemTime::now() });
+        }
+
+        println!("Failed to get GPS location. Falling back to Wi-Fi-based geolocation.");
+        if let Ok((lat, lon, accuracy_meters)) = wifi::get_geo_location().await {
+            return Ok(Location { lat, lon, altitude_meters: None, accuracy_meters, captured_at: SystemTime::now() });
+        }
+
+        println!("Failed to get Wi-Fi location. Falling back to IP-based geolocation.");
+        let client = Client::new();
+        let providers = ip::default_providers();
+        let (lat, lon, accuracy_meters) = ip::locate_via_providers(&providers, &client).await?;
+        Ok(Location { lat, lon, altitude_meters: None, accuracy_meters, captured_at: SystemTime::now() })
+    }
+
+    /// Queries GPS, Wi-Fi, and IP concurrently and stamps every fix with one
+    /// shared `observed_at`, taken once all three have answered, so no
+    /// source looks newer merely because it was queried later.
+    async fn locate_best_of_all(&self) -> Result<Location> {
+        let client = Client::new();
+        let providers = ip::default_providers();
+
+        let (gps_result, wifi_result, ip_result) = tokio::join!(
+            tokio::task::spawn_blocking(gps::get_gps_location),
+            wifi::get_geo_location(),
+            ip::locate_via_providers(&providers, &client),
+        );
+
+        let observed_at = SystemTime::now();
+        let mut fixes = Vec::new();
+
+        if let Ok(Ok((lat, lon, altitude_meters, accuracy_meters))) = gps_result {
+            fixes.push(SourceFix {
+                lat,
+                lon,
+                altitude_meters,
+                accuracy_meters,
+                observed_at,
+                source: LocationSource::Gps,
+            });
+        }
+
+        if let Ok((lat, lon, accuracy_meters)) = wifi_result {
+            fixes.push(SourceFix {
+                lat,
+                lon,
+                altitude_meters: None,
+                accuracy_meters,
+                observed_at,
+                source: LocationSource::Wifi,
+            });
+        }
+
+        if let Ok((lat, lon, accuracy_meters)) = ip_result {
+            fixes.push(SourceFix {
+                lat,
+                lon,
+                altitude_meters: None,
+                accuracy_meters,
+                observed_at,
+                source: LocationSource::Ip,
+            });
+        }
+
+        let best = select_best(&fixes, self.max_accuracy_meters)
+            .ok_or_else(|| anyhow::anyhow!("Failed to get location"))?;
+
+        println!("Best-of-all arbitration picked the {:?} fix", best.source);
+
+        Ok(Location {
+            lat: best.lat,
+            lon: best.lon,
+            altitude_meters: best.altitude_meters,
+            accuracy_meters: best.accuracy_meters,
+            captured_at: best.observed_at,
+        })
+    }
+}
+
+/// Picks the best fix out of `fixes`, discarding any whose accuracy
+/// exceeds `max_accuracy_meters`. Mirrors Chromium's network location
+/// arbitration: prefer the newer fix, but keep an older one when it is
+/// significantly more accurate.
+fn select_best(fixes: &[SourceFix], max_accuracy_meters: Option<f64>) -> Option<SourceFix> {
+    let candidates = fixes.iter().filter(|fix| match (fix.accuracy_meters, max_accuracy_meters) {
+        (Some(accuracy), Some(max)) => accuracy <= max,
+        _ => true,
+    });
+
+    let mut best: Option<&SourceFix> = None;
+    for candidate in candidates {
+        best = Some(match best {
+            None => candidate,
+            Some(current) => {
+                if candidate.observed_at >= current.observed_at {
+                    if is_significantly_less_accurate(candidate, current) {
+                        current
+                    } else {
+                        candidate
+                    }
+                } else if is_significantly_less_accurate(current, candidate) {
+                    candidate
+                } else {
+                    current
+                }
+            }
+        });
+    }
+
+    best.cloned()
+}
+
+fn is_significantly_less_accurate(fix: &SourceFix, than: &SourceFix) -> bool {
+    match (fix.accuracy_meters, than.accuracy_meters) {
+        (Some(fix_accuracy), Some(than_accuracy)) => {
+            fix_accuracy > than_accuracy * SIGNIFICANTLY_LESS_ACCURATE_FACTOR
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn fix(lat: f64, accuracy_meters: Option<f64>, observed_at: SystemTime, source: LocationSource) -> SourceFix {
+        SourceFix {
+            lat,
+            lon: 0.0,
+            altitude_meters: None,
+            accuracy_meters,
+            observed_at,
+            source,
+        }
+    }
+
+    #[test]
+    fn prefers_newer_fix_when_similarly_accurate() {
+        let now = SystemTime::now();
+        let older = fix(1.0, Some(50.0), now - Duration::from_secs(10), LocationSource::Wifi);
+        let newer = fix(2.0, Some(60.0), now, LocationSource::Gps);
+
+        let best = select_best(&[older, newer], None).unwrap();
+
+        assert_eq!(best.lat, 2.0);
+    }
+
+    #[test]
+    fn keeps_older_fix_when_significantly_more_accurate() {
+        let now = SystemTime::now();
+        let older = fix(1.0, Some(5.0), now - Duration::from_secs(10), LocationSource::Gps);
+        let newer = fix(2.0, Some(50.0), now, LocationSource::Wifi);
+
+        let best = select_best(&[older, newer], None).unwrap();
+
+        assert_eq!(best.lat, 1.0);
+    }
+
+    #[test]
+    fn discards_fixes_above_max_accuracy() {
+        let now = SystemTime::now();
+        let inaccurate = fix(1.0, Some(100_000.0), now, LocationSource::Ip);
+        let accurate = fix(2.0, Some(10.0), now - Duration::from_secs(5), LocationSource::Gps);
+
+        let best = select_best(&[inaccurate, accurate], Some(1_000.0)).unwrap();
+
+        assert_eq!(best.lat, 2.0);
+    }
+
+    #[test]
+    fn returns_none_when_all_fixes_discarded() {
+        let inaccurate = fix(1.0, Some(100_000.0), SystemTime::now(), LocationSource::Ip);
+
+        assert!(select_best(&[inaccurate], Some(1_000.0)).is_none());
+    }
+
+    #[test]
+    fn fixes_without_accuracy_are_never_discarded() {
+        let unknown = fix(1.0, None, SystemTime::now(), LocationSource::Gps);
+
+        assert!(select_best(&[unknown], Some(1.0)).is_some());
+    }
+}
@@ -0,0 +1,38 @@
+use anyhow::{Error, Result};
+use serde_json::Value;
+use std::process::Command;
+
+/// Reads a single GPS datum from `gpsd` via `gpspipe`.
+///
+/// Returns latitude, longitude, altitude in meters (gpsd's `alt` field, when
+/// present), and the horizontal accuracy in meters when `gpsd` reports one
+/// (its TPV report's `eph` field).
+pub fn get_gps_location() -> Result<(f64, f64, Option<f64>, Option<f64>), Error> {
+    let output = Command::new("gpspipe")
+        .arg("-w")
+        .arg("-n").arg("1")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("Failed to execute gpspipe command"));
+    }
+
+    // Convert GPS data to string
+    let gps_data = String::from_utf8_lossy(&output.stdout);
+    println!("GPS data: {}", gps_data); // Debugging purposes
+
+    let json: Value = serde_json::from_str(&gps_data)?;
+
+    // Extract latitude and longitude (adjust based on the actual JSON structure)
+    if let Some(lat) = json["lat"].as_f64() {
+        if let Some(lon) = json["lon"].as_f64() {
+            let altitude_meters = json["alt"].as_f64();
+            // gpsd's TPV report carries "eph", the estimated horizontal
+            // position error in meters, when the fix quality supports it.
+            let accuracy_meters = json["eph"].as_f64();
+            return Ok((lat, lon, altitude_meters, accuracy_meters));
+        }
+    }
+
+    Err(anyhow::anyhow!("Failed to extract GPS coordinates from JSON"))
+}
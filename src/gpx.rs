@@ -0,0 +1,155 @@
+use crate::Location;
+use anyhow::Result;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An in-memory track of fixes that can be exported as a GPX 1.1 file.
+pub struct GpxTrack {
+    points: Vec<Location>,
+}
+
+impl GpxTrack {
+    pub fn new() -> Self {
+        GpxTrack { points: Vec::new() }
+    }
+
+    pub fn push(&mut self, location: Location) {
+        self.points.push(location);
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Renders the track as a GPX 1.1 document: one `<trk><trkseg>` holding
+    /// one `<trkpt>` per recorded fix, with `<ele>` included when altitude
+    /// is known and an ISO-8601 `<time>` for every point.
+    pub fn to_gpx_string(&self) -> String {
+        let mut gpx = String::new();
+        gpx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        gpx.push_str(
+            "<gpx version=\"1.1\" creator=\"geolocator-new\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+        );
+        gpx.push_str("  <trk>\n    <trkseg>\n");
+
+        for point in &self.points {
+            gpx.push_str(&format!(
+                "      <trkpt lat=\"{}\" lon=\"{}\">\n",
+                point.lat, point.lon
+            ));
+
+            if let Some(altitude) = point.altitude_meters {
+                gpx.push_str(&format!("        <ele>{}</ele>\n", altitude));
+            }
+
+            gpx.push_str(&format!(
+                "        <time>{}</time>\n",
+                format_iso8601(point.captured_at)
+            ));
+
+            gpx.push_str("      </trkpt>\n");
+        }
+
+        gpx.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+        gpx
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<()> {
+        fs::write(path, self.to_gpx_string())?;
+        Ok(())
+    }
+}
+
+/// Formats a `SystemTime` as an ISO-8601 / RFC 3339 UTC timestamp, e.g.
+/// `2026-07-30T14:03:21Z`. Implemented by hand (civil-from-days, after
+/// Howard Hinnant's algorithm) since this crate has no date/time
+/// dependency.
+fn format_iso8601(time: SystemTime) -> String {
+    let secs_since_epoch = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let days = (secs_since_epoch / 86_400) as i64;
+    let secs_of_day = secs_since_epoch % 86_400;
+
+    let (year, month, day) = civil_from_days(days);
+    let hour = secs_of_day / 3600;
+    let minute = (secs_of_day % 3600) / 60;
+    let second = secs_of_day % 60;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// Converts a day count since the Unix epoch (1970-01-01) into a
+/// (year, month, day) civil date, following Howard Hinnant's
+/// `civil_from_days` algorithm.
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = (z - era * 146_097) as u64; // [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365; // [0, 399]
+    let y = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // [0, 365]
+    let mp = (5 * doy + 2) / 153; // [0, 11]
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32; // [1, 31]
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32; // [1, 12]
+    let y = if m <= 2 { y + 1 } else { y };
+
+    (y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn formats_the_unix_epoch() {
+        assert_eq!(format_iso8601(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn formats_a_known_date_and_time() {
+        // 2026-07-30T12:00:00Z
+        let time = UNIX_EPOCH + Duration::from_secs(1_785_412_800);
+        assert_eq!(format_iso8601(time), "2026-07-30T12:00:00Z");
+    }
+
+    #[test]
+    fn civil_from_days_handles_a_leap_day() {
+        // 2000-02-29 is a leap day (divisible by 400).
+        let time = UNIX_EPOCH + Duration::from_secs(951_782_400);
+        assert_eq!(format_iso8601(time), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn includes_elevation_only_when_known() {
+        let mut track = GpxTrack::new();
+        track.push(Location {
+            lat: 1.0,
+            lon: 2.0,
+            altitude_meters: Some(15.0),
+            accuracy_meters: None,
+            captured_at: UNIX_EPOCH,
+        });
+        track.push(Location {
+            lat: 3.0,
+            lon: 4.0,
+            altitude_meters: None,
+            accuracy_meters: None,
+            captured_at: UNIX_EPOCH,
+        });
+
+        let gpx = track.to_gpx_string();
+        assert_eq!(gpx.matches("<ele>").count(), 1);
+        assert_eq!(gpx.matches("<trkpt").count(), 2);
+    }
+}
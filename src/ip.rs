@@ -0,0 +1,338 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// A source of IP-based geolocation.
+///
+/// Implementations should fail fast (respecting [`REQUEST_TIMEOUT`]) so a
+/// slow or dead provider doesn't stall the whole chain in
+/// [`locate_via_providers`]. The returned accuracy is in meters; IP
+/// geolocation is typically city-level at best, so most providers report
+/// a coarse constant rather than a measured value.
+#[async_trait]
+pub trait IpProvider {
+    async fn locate(&self, client: &Client) -> Result<(f64, f64, Option<f64>)>;
+}
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Typical accuracy of city-level IP geolocation, used by providers that
+/// don't report a measured radius of their own.
+const CITY_LEVEL_ACCURACY_METERS: f64 = 50_000.0;
+
+#[derive(Deserialize, Debug)]
+struct IpInfoResponse {
+    loc: Option<String>,
+}
+
+/// Default provider, backed by `ipinfo.io/json`.
+pub struct IpInfoProvider;
+
+#[async_trait]
+impl IpProvider for IpInfoProvider {
+    async fn locate(&self, client: &Client) -> Result<(f64, f64, Option<f64>)> {
+        let response = client
+            .get("https://ipinfo.io/json")
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("ipinfo.io returned {}", response.status()));
+        }
+
+        let ip_info: IpInfoResponse = response.json().await?;
+        parse_ipinfo_response(ip_info)
+    }
+}
+
+/// Parses the `"lat,lon"` `loc` field out of an `ipinfo.io/json` response.
+fn parse_ipinfo_response(ip_info: IpInfoResponse) -> Result<(f64, f64, Option<f64>)> {
+    let loc = ip_info
+        .loc
+        .ok_or_else(|| anyhow::anyhow!("ipinfo.io response had no loc field"))?;
+
+    let loc_parts: Vec<&str> = loc.split(',').collect();
+    if loc_parts.len() != 2 {
+        return Err(anyhow::anyhow!("ipinfo.io returned an unparsable loc field"));
+    }
+
+    let lat = loc_parts[0]
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("Failed to parse latitude"))?;
+    let lon = loc_parts[1]
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("Failed to parse longitude"))?;
+
+    Ok((lat, lon, Some(CITY_LEVEL_ACCURACY_METERS)))
+}
+
+#[derive(Deserialize, Debug)]
+struct IpGeolocationResponse {
+    latitude: String,
+    longitude: String,
+}
+
+/// Provider backed by `ipgeolocation.io`, which requires an API key.
+pub struct IpGeolocationProvider {
+    pub api_key: String,
+}
+
+#[async_trait]
+impl IpProvider for IpGeolocationProvider {
+    async fn locate(&self, client: &Client) -> Result<(f64, f64, Option<f64>)> {
+        let response = client
+            .get("https://api.ipgeolocation.io/ipgeo")
+            .query(&[
+                ("apiKey", self.api_key.as_str()),
+                ("fields", "latitude,longitude"),
+            ])
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "ipgeolocation.io returned {}",
+                response.status()
+            ));
+        }
+
+        let geo: IpGeolocationResponse = response.json().await?;
+        parse_ipgeolocation_response(geo)
+    }
+}
+
+/// Parses the string-typed `latitude`/`longitude` fields out of an
+/// `ipgeolocation.io` response.
+fn parse_ipgeolocation_response(geo: IpGeolocationResponse) -> Result<(f64, f64, Option<f64>)> {
+    let lat = geo
+        .latitude
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("Failed to parse latitude"))?;
+    let lon = geo
+        .longitude
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("Failed to parse longitude"))?;
+
+    Ok((lat, lon, Some(CITY_LEVEL_ACCURACY_METERS)))
+}
+
+#[derive(Deserialize, Debug)]
+struct IpApiResponse {
+    status: String,
+    lat: Option<f64>,
+    lon: Option<f64>,
+}
+
+/// Free provider backed by `ip-api.com`. Useful as a last resort since it
+/// needs no API key but is rate-limited.
+pub struct IpApiProvider;
+
+#[async_trait]
+impl IpProvider for IpApiProvider {
+    async fn locate(&self, client: &Client) -> Result<(f64, f64, Option<f64>)> {
+        let response = client
+            .get("http://ip-api.com/json")
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("ip-api.com returned {}", response.status()));
+        }
+
+        let info: IpApiResponse = response.json().await?;
+        parse_ipapi_response(info)
+    }
+}
+
+/// Parses an `ip-api.com` response, rejecting non-`"success"` statuses and
+/// missing coordinates.
+fn parse_ipapi_response(info: IpApiResponse) -> Result<(f64, f64, Option<f64>)> {
+    if info.status != "success" {
+        return Err(anyhow::anyhow!("ip-api.com reported failure status"));
+    }
+
+    match (info.lat, info.lon) {
+        (Some(lat), Some(lon)) => Ok((lat, lon, Some(CITY_LEVEL_ACCURACY_METERS))),
+        _ => Err(anyhow::anyhow!("ip-api.com response had no coordinates")),
+    }
+}
+
+/// Name used to select each provider in `IP_PROVIDER_ORDER`.
+const PROVIDER_NAME_IPINFO: &str = "ipinfo";
+const PROVIDER_NAME_IPGEOLOCATION: &str = "ipgeolocation";
+const PROVIDER_NAME_IPAPI: &str = "ipapi";
+
+/// Parses a comma-separated `IP_PROVIDER_ORDER` value into trimmed,
+/// non-empty provider names, preserving order and duplicates.
+fn parse_provider_order(order: &str) -> Vec<&str> {
+    order.split(',').map(|name| name.trim()).filter(|name| !name.is_empty()).collect()
+}
+
+/// Builds one named provider, returning `None` when it can't be
+/// constructed (currently only `ipgeolocation`, which needs an API key).
+fn build_provider(name: &str) -> Option<Box<dyn IpProvider + Send + Sync>> {
+    match name {
+        PROVIDER_NAME_IPINFO => Some(Box::new(IpInfoProvider)),
+        PROVIDER_NAME_IPGEOLOCATION => std::env::var("IPGEOLOCATION_API_KEY")
+            .ok()
+            .map(|api_key| Box::new(IpGeolocationProvider { api_key }) as Box<dyn IpProvider + Send + Sync>),
+        PROVIDER_NAME_IPAPI => Some(Box::new(IpApiProvider)),
+        other => {
+            eprintln!("Ignoring unknown IP provider in IP_PROVIDER_ORDER: {}", other);
+            None
+        }
+    }
+}
+
+/// Builds the provider chain used by [`locate_via_providers`].
+///
+/// Users can override the order (or drop providers entirely) by setting
+/// `IP_PROVIDER_ORDER` to a comma-separated list of provider names
+/// (`ipinfo`, `ipgeolocation`, `ipapi`), e.g. `IP_PROVIDER_ORDER=ipapi,ipinfo`.
+/// Otherwise the default chain is ipinfo.io first, then ip-api.com;
+/// `ipgeolocation.io` is only included when an API key is configured via
+/// the `IPGEOLOCATION_API_KEY` environment variable, since it is otherwise
+/// guaranteed to fail.
+pub fn default_providers() -> Vec<Box<dyn IpProvider + Send + Sync>> {
+    if let Ok(order) = std::env::var("IP_PROVIDER_ORDER") {
+        return parse_provider_order(&order).into_iter().filter_map(build_provider).collect();
+    }
+
+    let mut providers: Vec<Box<dyn IpProvider + Send + Sync>> = vec![Box::new(IpInfoProvider)];
+
+    if let Ok(api_key) = std::env::var("IPGEOLOCATION_API_KEY") {
+        providers.push(Box::new(IpGeolocationProvider { api_key }));
+    }
+
+    providers.push(Box::new(IpApiProvider));
+
+    providers
+}
+
+/// Tries each provider in order, returning the first successful fix.
+pub async fn locate_via_providers(
+    providers: &[Box<dyn IpProvider + Send + Sync>],
+    client: &Client,
+) -> Result<(f64, f64, Option<f64>)> {
+    for provider in providers {
+        if let Ok(fix) = provider.locate(client).await {
+            return Ok(fix);
+        }
+    }
+
+    Err(anyhow::anyhow!("All IP geolocation providers failed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ipinfo_loc_field() {
+        let (lat, lon, accuracy) = parse_ipinfo_response(IpInfoResponse {
+            loc: Some("37.751,-97.822".to_string()),
+        })
+        .unwrap();
+        assert_eq!(lat, 37.751);
+        assert_eq!(lon, -97.822);
+        assert_eq!(accuracy, Some(CITY_LEVEL_ACCURACY_METERS));
+    }
+
+    #[test]
+    fn rejects_ipinfo_response_with_no_loc_field() {
+        assert!(parse_ipinfo_response(IpInfoResponse { loc: None }).is_err());
+    }
+
+    #[test]
+    fn rejects_ipinfo_response_with_unparsable_loc_field() {
+        assert!(parse_ipinfo_response(IpInfoResponse {
+            loc: Some("37.751".to_string()),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn parses_ipgeolocation_response() {
+        let (lat, lon, accuracy) = parse_ipgeolocation_response(IpGeolocationResponse {
+            latitude: "37.751".to_string(),
+            longitude: "-97.822".to_string(),
+        })
+        .unwrap();
+        assert_eq!(lat, 37.751);
+        assert_eq!(lon, -97.822);
+        assert_eq!(accuracy, Some(CITY_LEVEL_ACCURACY_METERS));
+    }
+
+    #[test]
+    fn rejects_ipgeolocation_response_with_unparsable_coordinates() {
+        assert!(parse_ipgeolocation_response(IpGeolocationResponse {
+            latitude: "not-a-number".to_string(),
+            longitude: "-97.822".to_string(),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn parses_ipapi_response() {
+        let (lat, lon, accuracy) = parse_ipapi_response(IpApiResponse {
+            status: "success".to_string(),
+            lat: Some(37.751),
+            lon: Some(-97.822),
+        })
+        .unwrap();
+        assert_eq!(lat, 37.751);
+        assert_eq!(lon, -97.822);
+        assert_eq!(accuracy, Some(CITY_LEVEL_ACCURACY_METERS));
+    }
+
+    #[test]
+    fn rejects_ipapi_response_with_failure_status() {
+        assert!(parse_ipapi_response(IpApiResponse {
+            status: "fail".to_string(),
+            lat: None,
+            lon: None,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_ipapi_response_with_missing_coordinates() {
+        assert!(parse_ipapi_response(IpApiResponse {
+            status: "success".to_string(),
+            lat: None,
+            lon: None,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn parses_comma_separated_provider_names() {
+        assert_eq!(parse_provider_order("ipapi,ipinfo"), vec!["ipapi", "ipinfo"]);
+    }
+
+    #[test]
+    fn trims_whitespace_and_drops_empty_entries() {
+        assert_eq!(parse_provider_order(" ipapi , , ipinfo "), vec!["ipapi", "ipinfo"]);
+    }
+
+    #[test]
+    fn empty_order_yields_no_providers() {
+        assert!(parse_provider_order("").is_empty());
+    }
+
+    #[test]
+    fn build_provider_ignores_unknown_names() {
+        assert!(build_provider("bogus").is_none());
+    }
+
+    #[test]
+    fn build_provider_constructs_providers_that_need_no_credentials() {
+        assert!(build_provider(PROVIDER_NAME_IPINFO).is_some());
+        assert!(build_provider(PROVIDER_NAME_IPAPI).is_some());
+    }
+}
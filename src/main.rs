@@ -1,201 +1,294 @@
+mod arbitrator;
+mod gps;
+mod gpx;
+mod ip;
+mod wifi;
+
+use anyhow::Result;
+use arbitrator::{ArbitrationMode, LocationArbitrator};
 use serde::{Deserialize, Serialize};
-use anyhow::{Error, Result};
-use serde_json::Value;
-use std::process::Command;
-use dotenv::dotenv;
-use reqwest::Client;
-
-
-#[derive(Debug, Deserialize)]
-struct GoogleGeoResponse {
-    location: GoogleLocation,
-    accuracy: f64,
-}
-
-#[derive(Debug, Deserialize)]
-struct GoogleLocation {
-    lat: f64,
-    lng: f64,
-}
-
-#[derive(Debug, Serialize)]
-struct WifiAccessPoint {
-    macAddress: String,
-    signalStrength: i32,
-}
-
-#[derive(Debug, Serialize)]
-struct GeoRequest {
-    considerIp: bool,
-    wifiAccessPoints: Vec<WifiAccessPoint>,
-}
-
-
-#[derive(Deserialize, Debug)]
-struct IpLocation {
-    loc: Option<String>,
-}
-
-type Coordinates = (i32, i32);
+use std::time::{Duration, SystemTime};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Location {
-    coordinates: Coordinates,
+    pub lat: f64,
+    pub lon: f64,
+    pub altitude_meters: Option<f64>,
+    pub accuracy_meters: Option<f64>,
+    pub captured_at: SystemTime,
 }
 
 impl Location {
-    pub async fn get_location() -> Result<Location> {
-        // Try getting GPS location first
-        if let Ok((lat, lon)) = get_gps_location() {
-            Ok(Location {
-                coordinates: f64_to_i32_coordinates(lat, lon),
-            })
-        } else if let Ok((lat, lon)) = get_geo_location().await {
-            // Fallback to IP-based geolocation
-            println!("Failed to get GPS location. Falling back to IP-based geolocation.");
-            Ok(Location {
-                coordinates: f64_to_i32_coordinates(lat, lon),
-            })
-        } else if let Ok((lat, lon)) = get_ip_location().await {
-            // Fallback to IP-based geolocation
-            println!("Failed to get GPS location. Falling back to IP-based geolocation.");
-            Ok(Location {
-                coordinates: f64_to_i32_coordinates(lat, lon),
-            })
+    /// Queries sources according to `mode`, optionally discarding fixes
+    /// less accurate than `max_accuracy_meters` when arbitrating.
+    pub async fn get_location_with_arbitration(
+        mode: ArbitrationMode,
+        max_accuracy_meters: Option<f64>,
+    ) -> Result<Location> {
+        let mut arbitrator = LocationArbitrator::new(mode);
+        if let Some(max_accuracy_meters) = max_accuracy_meters {
+            arbitrator = arbitrator.with_max_accuracy_meters(max_accuracy_meters);
         }
+        arbitrator.locate().await
+    }
 
-        else {
-            Err(anyhow::anyhow!("Failed to get location"))
+    /// Formats this location as an RFC 5870 `geo:` URI, e.g.
+    /// `geo:37.786971,-122.399677;u=65`. Altitude, when known, is included
+    /// as a third comma-separated coordinate; accuracy, when known, is
+    /// appended as the `u` (uncertainty) parameter.
+    pub fn to_geo_uri(&self) -> String {
+        let mut uri = match self.altitude_meters {
+            Some(altitude) => format!("geo:{},{},{}", self.lat, self.lon, altitude),
+            None => format!("geo:{},{}", self.lat, self.lon),
+        };
+
+        if let Some(accuracy) = self.accuracy_meters {
+            uri.push_str(&format!(";u={}", accuracy));
         }
+
+        uri
     }
-}
 
-fn f64_to_i32_coordinates(lat: f64, lon: f64) -> Coordinates {
-    let lat_i32 = (lat * 1_000_000.0).round() as i32;
-    let lon_i32 = (lon * 1_000_000.0).round() as i32;
+    /// Parses an RFC 5870 `geo:` URI produced by [`Location::to_geo_uri`],
+    /// validating the scheme and the latitude/longitude ranges.
+    pub fn from_geo_uri(uri: &str) -> Result<Location> {
+        let rest = uri
+            .strip_prefix("geo:")
+            .ok_or_else(|| anyhow::anyhow!("Not a geo: URI"))?;
+
+        let (coords, params) = match rest.split_once(';') {
+            Some((coords, params)) => (coords, Some(params)),
+            None => (rest, None),
+        };
+
+        let parts: Vec<&str> = coords.split(',').collect();
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(anyhow::anyhow!("geo: URI must have 2 or 3 coordinates"));
+        }
 
-    (lat_i32, lon_i32)
-}
+        let lat = parts[0]
+            .parse::<f64>()
+            .map_err(|_| anyhow::anyhow!("Invalid latitude in geo: URI"))?;
+        let lon = parts[1]
+            .parse::<f64>()
+            .map_err(|_| anyhow::anyhow!("Invalid longitude in geo: URI"))?;
 
-fn get_gps_location() -> Result<(f64, f64), Error> {
-    // Use gpspipe to get single GPS datum
-    let output = Command::new("gpspipe")
-        .arg("-w")
-        .arg("-n").arg("1")
-        .output()?;
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(anyhow::anyhow!("Latitude out of range"));
+        }
+        if !(-180.0..=180.0).contains(&lon) {
+            return Err(anyhow::anyhow!("Longitude out of range"));
+        }
 
-    if !output.status.success() {
-        return Err(anyhow::anyhow!("Failed to execute gpspipe command"));
+        let altitude_meters = match parts.get(2) {
+            Some(altitude) => Some(
+                altitude
+                    .parse::<f64>()
+                    .map_err(|_| anyhow::anyhow!("Invalid altitude in geo: URI"))?,
+            ),
+            None => None,
+        };
+
+        let accuracy_meters = params
+            .and_then(|params| params.split(';').find_map(|param| param.strip_prefix("u=")))
+            .and_then(|u| u.parse::<f64>().ok());
+
+        Ok(Location {
+            lat,
+            lon,
+            altitude_meters,
+            accuracy_meters,
+            captured_at: SystemTime::now(),
+        })
     }
+}
 
-    // Convert GPS data to string
-    let gps_data = String::from_utf8_lossy(&output.stdout);
-    println!("GPS data: {}", gps_data); // Debugging purposes
-
-    let json: Value = serde_json::from_str(&gps_data)?;
+/// Arbitration settings parsed from `--mode` and `--max-accuracy` flags,
+/// which are accepted by both the one-shot and `track` invocations.
+struct ArbitrationFlags {
+    mode: ArbitrationMode,
+    max_accuracy_meters: Option<f64>,
+}
 
-    // Extract latitude and longitude (adjust based on the actual JSON structure)
-    if let Some(lat) = json["lat"].as_f64() {
-        if let Some(lon) = json["lon"].as_f64() {
-            return Ok((lat, lon));
+/// Parses `--mode <fast|best-of-all>` and `--max-accuracy <meters>` out of
+/// `args`, returning the flags and the remaining positional arguments.
+fn parse_arbitration_flags(args: &[String]) -> Result<(ArbitrationFlags, Vec<String>)> {
+    let mut mode = ArbitrationMode::FastFirstSuccess;
+    let mut max_accuracy_meters = None;
+    let mut positional = Vec::new();
+
+    let mut iter = args.iter().cloned();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--mode" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--mode requires a value"))?;
+                mode = match value.as_str() {
+                    "fast" => ArbitrationMode::FastFirstSuccess,
+                    "best-of-all" => ArbitrationMode::BestOfAll,
+                    other => return Err(anyhow::anyhow!("Unknown --mode: {}", other)),
+                };
+            }
+            "--max-accuracy" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--max-accuracy requires a value"))?;
+                max_accuracy_meters = Some(
+                    value
+                        .parse::<f64>()
+                        .map_err(|_| anyhow::anyhow!("Invalid --max-accuracy: {}", value))?,
+                );
+            }
+            _ => positional.push(arg),
         }
     }
 
-    Err(anyhow::anyhow!("Failed to extract GPS coordinates from JSON"))
+    Ok((ArbitrationFlags { mode, max_accuracy_meters }, positional))
 }
 
-async fn get_ip_location() -> Result<(f64, f64), Error> {
-    let url = "https://ipinfo.io/json";
-    let response = reqwest::get(url).await?;
+/// Asks for a location fix `iterations` times, `interval` apart, then writes
+/// the fixes that succeeded out as a GPX 1.1 track. Failed attempts are
+/// skipped, not retried, so the track can hold fewer than `iterations`
+/// points; nothing is written when no attempt succeeded.
+async fn run_track_logger(
+    interval: Duration,
+    iterations: usize,
+    output_path: &str,
+    flags: &ArbitrationFlags,
+) -> Result<()> {
+    let mut track = gpx::GpxTrack::new();
+
+    for i in 0..iterations {
+        match Location::get_location_with_arbitration(flags.mode, flags.max_accuracy_meters).await {
+            Ok(location) => {
+                println!("Recorded fix {}/{}: {:?}", i + 1, iterations, location);
+                track.push(location);
+            }
+            Err(e) => eprintln!("Failed to get a fix: {}", e),
+        }
 
-    if response.status().is_success() {
-        let ip_info: IpLocation = response.json().await?;
+        if i + 1 < iterations {
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    if track.is_empty() {
+        eprintln!("No fixes were recorded; not writing {}", output_path);
+        return Ok(());
+    }
+
+    track.write_to_file(output_path)?;
+    println!("Wrote {} track points to {}", track.len(), output_path);
+    Ok(())
+}
 
-        let loc = ip_info
-            .loc
-            .ok_or_else(|| anyhow::anyhow!("Failed to get location via IP."))?;
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (flags, args) = parse_arbitration_flags(&args)?;
 
-        let loc_parts: Vec<&str> = loc.split(',').collect();
+    if args.first().map(String::as_str) == Some("track") {
+        let interval_secs: u64 = args.get(1).and_then(|s| s.parse().ok()).unwrap_or(10);
+        let iterations: usize = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(6);
+        let output_path = args.get(3).map(String::as_str).unwrap_or("track.gpx");
 
-        if loc_parts.len() == 2 {
-            let lat = loc_parts[0]
-                .parse::<f64>()
-                .map_err(|_| anyhow::anyhow!("Failed to parse latitude"))?;
-            let lon = loc_parts[1]
-                .parse::<f64>()
-                .map_err(|_| anyhow::anyhow!("Failed to parse longitude"))?;
+        return run_track_logger(Duration::from_secs(interval_secs), iterations, output_path, &flags).await;
+    }
 
-            return Ok((lat, lon));
+    match Location::get_location_with_arbitration(flags.mode, flags.max_accuracy_meters).await {
+        Ok(location) => {
+            println!("Got location: {:?}", location);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
         }
-
-        Err(anyhow::anyhow!("Failed to get location via IP."))
-    } else {
-        Err(anyhow::anyhow!("Failed to get location via IP."))
     }
+    Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(lat: f64, lon: f64, altitude_meters: Option<f64>, accuracy_meters: Option<f64>) -> Location {
+        Location {
+            lat,
+            lon,
+            altitude_meters,
+            accuracy_meters,
+            captured_at: SystemTime::now(),
+        }
+    }
 
-pub async fn get_geo_location() -> Result<(f64, f64), Error> {
-    dotenv().ok();
-    let geo_api = std::env::var("GEO_API")?;
-
-    let output = Command::new("nmcli")
-        .args(&["-t", "-f", "SSID,BSSID,SIGNAL", "dev", "wifi"])
-        .output()?;
-    let stdout = String::from_utf8_lossy(&output.stdout);
+    #[test]
+    fn formats_lat_lon_only() {
+        let uri = location(37.786971, -122.399677, None, None).to_geo_uri();
+        assert_eq!(uri, "geo:37.786971,-122.399677");
+    }
 
-    let mut wifi_list = Vec::new();
+    #[test]
+    fn formats_altitude_and_accuracy() {
+        let uri = location(1.0, 2.0, Some(15.0), Some(65.0)).to_geo_uri();
+        assert_eq!(uri, "geo:1,2,15;u=65");
+    }
 
-    for line in stdout.lines() {
-        let parts: Vec<&str> = line.split(':').collect();
-        if parts.len() >= 3 {
-            let bssid_parts = &parts[1..parts.len() - 1];
-            let bssid = bssid_parts.join(":").replace("\\:", ":");
+    #[test]
+    fn round_trips_lat_lon() {
+        let original = location(37.786971, -122.399677, None, None);
+        let parsed = Location::from_geo_uri(&original.to_geo_uri()).unwrap();
+        assert_eq!(parsed.lat, original.lat);
+        assert_eq!(parsed.lon, original.lon);
+        assert_eq!(parsed.altitude_meters, None);
+        assert_eq!(parsed.accuracy_meters, None);
+    }
 
-            let signal_str = parts.last().unwrap_or(&"0");
-            let signal = signal_str.parse::<i32>().unwrap_or(0);
+    #[test]
+    fn round_trips_altitude_and_accuracy() {
+        let original = location(1.0, 2.0, Some(15.0), Some(65.0));
+        let parsed = Location::from_geo_uri(&original.to_geo_uri()).unwrap();
+        assert_eq!(parsed.altitude_meters, Some(15.0));
+        assert_eq!(parsed.accuracy_meters, Some(65.0));
+    }
 
-            wifi_list.push(WifiAccessPoint {
-                macAddress: bssid.to_uppercase(),
-                signalStrength: -signal, // Google expects negative RSSI
-            });
-        }
+    #[test]
+    fn rejects_wrong_scheme() {
+        assert!(Location::from_geo_uri("http:37.8,-122.4").is_err());
     }
 
-    if wifi_list.is_empty() {
-        return Err(anyhow::anyhow!("No Wi-Fi networks found"));
+    #[test]
+    fn rejects_out_of_range_latitude() {
+        assert!(Location::from_geo_uri("geo:200,0").is_err());
     }
 
-    let geo_request = GeoRequest {
-        considerIp: true,
-        wifiAccessPoints: wifi_list,
-    };
+    #[test]
+    fn rejects_out_of_range_longitude() {
+        assert!(Location::from_geo_uri("geo:0,200").is_err());
+    }
 
-    let url = format!(
-        "https://www.googleapis.com/geolocation/v1/geolocate?key={}",
-        geo_api
-    );
-    let client = Client::new();
-    let resp: GoogleGeoResponse = client
-        .post(&url)
-        .json(&geo_request)
-        .send()
-        .await?
-        .json()
-        .await?;
+    #[test]
+    fn rejects_malformed_coordinates() {
+        assert!(Location::from_geo_uri("geo:not-a-number,0").is_err());
+    }
 
-    Ok((resp.location.lat, resp.location.lng))
-}
+    #[test]
+    fn parses_mode_and_max_accuracy_flags() {
+        let args = vec![
+            "--mode".to_string(),
+            "best-of-all".to_string(),
+            "--max-accuracy".to_string(),
+            "50".to_string(),
+            "track".to_string(),
+        ];
+        let (flags, positional) = parse_arbitration_flags(&args).unwrap();
+        assert_eq!(flags.mode, ArbitrationMode::BestOfAll);
+        assert_eq!(flags.max_accuracy_meters, Some(50.0));
+        assert_eq!(positional, vec!["track".to_string()]);
+    }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    match Location::get_location().await {
-        Ok(location) => {
-            println!("Got location: {:?}", location);
-        }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-        }
+    #[test]
+    fn rejects_unknown_mode() {
+        let args = vec!["--mode".to_string(), "bogus".to_string()];
+        assert!(parse_arbitration_flags(&args).is_err());
     }
-    Ok(())
 }
@@ -0,0 +1,389 @@
+use anyhow::{Error, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// A single Wi-Fi access point observed by a scan, ready to be sent to the
+/// Google Geolocation API. `age`, `channel`, and `signalToNoiseRatio` are
+/// all optional per the API schema and are omitted when a scanner can't
+/// supply them. `channel` is available from every scanner; `signalToNoiseRatio`
+/// is only available from `iwlist`, and only when the driver reports a
+/// noise floor, since neither `nmcli` nor `airport` expose one.
+#[derive(Debug, Serialize)]
+pub struct WifiAccessPoint {
+    pub macAddress: String,
+    pub signalStrength: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signalToNoiseRatio: Option<i32>,
+}
+
+/// Something that can produce a snapshot of nearby Wi-Fi access points.
+///
+/// Implementations are expected to be cheap to construct and to perform the
+/// actual scan (which may shell out to a platform tool) inside `scan`.
+pub trait WifiScanner {
+    fn scan(&self) -> Result<Vec<WifiAccessPoint>>;
+}
+
+/// Linux scanner backed by `nmcli`, the primary backend on NetworkManager
+/// systems.
+pub struct NmcliScanner;
+
+impl WifiScanner for NmcliScanner {
+    fn scan(&self) -> Result<Vec<WifiAccessPoint>> {
+        let output = Command::new("nmcli")
+            .args(&["-t", "-f", "SSID,BSSID,SIGNAL,CHAN", "dev", "wifi"])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("nmcli exited with a non-zero status"));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let wifi_list: Vec<WifiAccessPoint> = stdout.lines().filter_map(parse_nmcli_line).collect();
+
+        if wifi_list.is_empty() {
+            return Err(anyhow::anyhow!("No Wi-Fi networks found"));
+        }
+
+        Ok(wifi_list)
+    }
+}
+
+/// Parses one `nmcli -t -f SSID,BSSID,SIGNAL,CHAN dev wifi` line, e.g.
+/// `MyNetwork:AA\:BB\:CC\:DD\:EE\:FF:72:6`. `nmcli`'s `-t` output escapes
+/// colons inside field values with a backslash, which is why the BSSID is
+/// reassembled from however many colon-separated parts remain once the
+/// trailing signal and channel fields are peeled off.
+fn parse_nmcli_line(line: &str) -> Option<WifiAccessPoint> {
+    let parts: Vec<&str> = line.split(':').collect();
+    if parts.len() < 4 {
+        return None;
+    }
+
+    let bssid_parts = &parts[1..parts.len() - 2];
+    let bssid = bssid_parts.join(":").replace("\\:", ":");
+
+    let signal_str = parts[parts.len() - 2];
+    let signal = signal_str.parse::<i32>().unwrap_or(0);
+
+    let channel = parts.last().and_then(|s| s.parse::<u32>().ok());
+
+    Some(WifiAccessPoint {
+        macAddress: bssid.to_uppercase(),
+        signalStrength: -signal, // Google expects negative RSSI
+        age: Some(0),
+        channel,
+        signalToNoiseRatio: None,
+    })
+}
+
+/// Linux fallback scanner backed by `iwlist`, used when `nmcli` is not on
+/// `PATH` (e.g. on minimal installs that only ship `wireless-tools`).
+pub struct IwlistScanner {
+    pub interface: String,
+}
+
+impl WifiScanner for IwlistScanner {
+    fn scan(&self) -> Result<Vec<WifiAccessPoint>> {
+        let output = Command::new("iwlist")
+            .arg(&self.interface)
+            .arg("scan")
+            .output()?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("iwlist exited with a non-zero status"));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let wifi_list = parse_iwlist_output(&stdout);
+
+        if wifi_list.is_empty() {
+            return Err(anyhow::anyhow!("No Wi-Fi networks found"));
+        }
+
+        Ok(wifi_list)
+    }
+}
+
+/// Parses the `Cell ... - Address: ...` / `Channel:` / `Frequency:` /
+/// `Quality=... Signal level=... [Noise level=...]` blocks out of full
+/// `iwlist <iface> scan` output. One access point is emitted per `Cell`
+/// block that reaches a signal level line.
+fn parse_iwlist_output(stdout: &str) -> Vec<WifiAccessPoint> {
+    let mut wifi_list = Vec::new();
+    let mut current_bssid: Option<String> = None;
+    let mut current_channel: Option<u32> = None;
+
+    for line in stdout.lines() {
+        let line = line.trim();
+
+        if let Some(addr) = line.strip_prefix("Cell ").and_then(|rest| rest.split("Address: ").nth(1)) {
+            current_bssid = Some(addr.trim().to_uppercase());
+            current_channel = None;
+        } else if let Some(rest) = line.strip_prefix("Channel:") {
+            current_channel = rest.trim().parse::<u32>().ok();
+        } else if let Some(channel) = parse_channel_from_frequency_line(line) {
+            current_channel = Some(channel);
+        } else if let Some(signal) = parse_iwlist_signal_level(line) {
+            if let Some(bssid) = current_bssid.take() {
+                // Older drivers report a noise floor alongside the signal
+                // level on the same "Quality=... Signal level=... Noise
+                // level=..." line; newer ones omit it.
+                let signal_to_noise_ratio = parse_iwlist_noise_level(line).map(|noise| signal - noise);
+
+                wifi_list.push(WifiAccessPoint {
+                    macAddress: bssid,
+                    signalStrength: signal,
+                    age: Some(0),
+                    channel: current_channel.take(),
+                    signalToNoiseRatio: signal_to_noise_ratio,
+                });
+            }
+        }
+    }
+
+    wifi_list
+}
+
+/// Parses the channel out of an `iwlist` `Frequency:` line, e.g.
+/// `Frequency:2.412 GHz (Channel 1)`. Some `iwlist` builds only report the
+/// channel this way and never emit a standalone `Channel:` line.
+fn parse_channel_from_frequency_line(line: &str) -> Option<u32> {
+    let rest = line.strip_prefix("Frequency:")?;
+    let inside = rest.split("(Channel ").nth(1)?;
+    let digits: String = inside.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse::<u32>().ok()
+}
+
+fn parse_iwlist_signal_level(line: &str) -> Option<i32> {
+    parse_dbm_field(line, "Signal level=")
+}
+
+fn parse_iwlist_noise_level(line: &str) -> Option<i32> {
+    parse_dbm_field(line, "Noise level=")
+}
+
+/// Finds `prefix` anywhere in `line` and parses the signed integer (dBm
+/// value) that immediately follows it, e.g. `parse_dbm_field(line,
+/// "Signal level=")` on `"Quality=70/70  Signal level=-40 dBm"` yields
+/// `Some(-40)`.
+fn parse_dbm_field(line: &str, prefix: &str) -> Option<i32> {
+    let idx = line.find(prefix)?;
+    let rest = &line[idx + prefix.len()..];
+    let digits: String = rest.chars().take_while(|c| *c == '-' || c.is_ascii_digit()).collect();
+    digits.parse::<i32>().ok()
+}
+
+fn is_mac_address(field: &str) -> bool {
+    let groups: Vec<&str> = field.split(':').collect();
+    groups.len() == 6 && groups.iter().all(|g| g.len() == 2 && g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// macOS scanner backed by the `airport` utility (the CLI front-end for the
+/// private Apple80211 framework).
+pub struct AirportScanner;
+
+const AIRPORT_PATH: &str =
+    "/System/Library/PrivateFrameworks/Apple80211.framework/Versions/Current/Resources/airport";
+
+impl WifiScanner for AirportScanner {
+    fn scan(&self) -> Result<Vec<WifiAccessPoint>> {
+        let output = Command::new(AIRPORT_PATH).arg("-s").output()?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("airport exited with a non-zero status"));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines();
+        // First line is the column header (SSID BSSID RSSI CHANNEL ...).
+        lines.next();
+
+        let wifi_list: Vec<WifiAccessPoint> = lines.filter_map(parse_airport_line).collect();
+
+        if wifi_list.is_empty() {
+            return Err(anyhow::anyhow!("No Wi-Fi networks found"));
+        }
+
+        Ok(wifi_list)
+    }
+}
+
+/// Parses one data row of `airport -s` output, e.g.
+/// `MyNetwork AA:BB:CC:DD:EE:FF -40  6,+1  N  US  WPA2(PSK/AES/AES)`.
+/// Columns are "SSID BSSID RSSI CHANNEL HT CC SECURITY"; the BSSID is found
+/// by shape rather than position since SSID may itself contain
+/// whitespace-separated words.
+fn parse_airport_line(line: &str) -> Option<WifiAccessPoint> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let bssid_idx = fields.iter().position(|f| is_mac_address(f))?;
+
+    let bssid = fields[bssid_idx];
+    let rssi = fields.get(bssid_idx + 1).copied().unwrap_or("0");
+    let channel = fields
+        .get(bssid_idx + 2)
+        .and_then(|c| c.split(',').next())
+        .and_then(|c| c.parse::<u32>().ok());
+
+    Some(WifiAccessPoint {
+        macAddress: bssid.to_uppercase(),
+        signalStrength: rssi.parse::<i32>().unwrap_or(0),
+        age: Some(0),
+        channel,
+        signalToNoiseRatio: None,
+    })
+}
+
+/// Picks the best scanner for the current platform: `nmcli` on Linux,
+/// falling back to `iwlist` if `nmcli` is missing; `airport` on macOS.
+pub fn default_scanner() -> Box<dyn WifiScanner> {
+    #[cfg(target_os = "macos")]
+    {
+        Box::new(AirportScanner)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if Command::new("which").arg("nmcli").output().map(|o| o.status.success()).unwrap_or(false) {
+            Box::new(NmcliScanner)
+        } else {
+            Box::new(IwlistScanner { interface: "wlan0".to_string() })
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        Box::new(NmcliScanner)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleGeoResponse {
+    location: GoogleLocation,
+    accuracy: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleLocation {
+    lat: f64,
+    lng: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct GeoRequest {
+    considerIp: bool,
+    wifiAccessPoints: Vec<WifiAccessPoint>,
+}
+
+pub async fn get_geo_location() -> Result<(f64, f64, Option<f64>), Error> {
+    dotenv::dotenv().ok();
+    let geo_api = std::env::var("GEO_API")?;
+
+    let wifi_list = default_scanner().scan()?;
+
+    let geo_request = GeoRequest {
+        considerIp: true,
+        wifiAccessPoints: wifi_list,
+    };
+
+    let url = format!(
+        "https://www.googleapis.com/geolocation/v1/geolocate?key={}",
+        geo_api
+    );
+    let client = Client::new();
+    let resp: GoogleGeoResponse = client
+        .post(&url)
+        .json(&geo_request)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    Ok((resp.location.lat, resp.location.lng, Some(resp.accuracy)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_an_nmcli_line() {
+        let ap = parse_nmcli_line("MyNetwork:AA\\:BB\\:CC\\:DD\\:EE\\:FF:72:6").unwrap();
+        assert_eq!(ap.macAddress, "AA:BB:CC:DD:EE:FF");
+        assert_eq!(ap.signalStrength, -72);
+        assert_eq!(ap.channel, Some(6));
+        assert_eq!(ap.signalToNoiseRatio, None);
+    }
+
+    #[test]
+    fn rejects_an_nmcli_line_with_too_few_fields() {
+        assert!(parse_nmcli_line("MyNetwork:72").is_none());
+    }
+
+    #[test]
+    fn parses_an_iwlist_block_with_a_channel_line_and_noise() {
+        let stdout = "Cell 01 - Address: AA:BB:CC:DD:EE:FF\n\
+                       Channel:6\n\
+                       Quality=70/70  Signal level=-40 dBm  Noise level=-90 dBm\n";
+        let aps = parse_iwlist_output(stdout);
+        assert_eq!(aps.len(), 1);
+        assert_eq!(aps[0].macAddress, "AA:BB:CC:DD:EE:FF");
+        assert_eq!(aps[0].signalStrength, -40);
+        assert_eq!(aps[0].channel, Some(6));
+        assert_eq!(aps[0].signalToNoiseRatio, Some(50));
+    }
+
+    #[test]
+    fn parses_an_iwlist_block_with_only_a_frequency_line_and_no_noise() {
+        let stdout = "Cell 01 - Address: AA:BB:CC:DD:EE:FF\n\
+                       Frequency:2.412 GHz (Channel 1)\n\
+                       Quality=70/70  Signal level=-40 dBm\n";
+        let aps = parse_iwlist_output(stdout);
+        assert_eq!(aps.len(), 1);
+        assert_eq!(aps[0].channel, Some(1));
+        assert_eq!(aps[0].signalToNoiseRatio, None);
+    }
+
+    #[test]
+    fn parses_multiple_iwlist_cells() {
+        let stdout = "Cell 01 - Address: AA:BB:CC:DD:EE:FF\n\
+                       Channel:6\n\
+                       Quality=70/70  Signal level=-40 dBm\n\
+                       Cell 02 - Address: 11:22:33:44:55:66\n\
+                       Channel:11\n\
+                       Quality=50/70  Signal level=-60 dBm\n";
+        let aps = parse_iwlist_output(stdout);
+        assert_eq!(aps.len(), 2);
+        assert_eq!(aps[1].macAddress, "11:22:33:44:55:66");
+        assert_eq!(aps[1].channel, Some(11));
+    }
+
+    #[test]
+    fn parses_an_airport_line() {
+        let ap = parse_airport_line("MyNetwork AA:BB:CC:DD:EE:FF -40  6,+1  N  US  WPA2(PSK/AES/AES)").unwrap();
+        assert_eq!(ap.macAddress, "AA:BB:CC:DD:EE:FF");
+        assert_eq!(ap.signalStrength, -40);
+        assert_eq!(ap.channel, Some(6));
+    }
+
+    #[test]
+    fn rejects_an_airport_line_with_no_bssid() {
+        assert!(parse_airport_line("not a real line").is_none());
+    }
+
+    #[test]
+    fn recognizes_a_valid_mac_address() {
+        assert!(is_mac_address("AA:BB:CC:DD:EE:FF"));
+    }
+
+    #[test]
+    fn rejects_strings_that_are_not_mac_addresses() {
+        assert!(!is_mac_address("MyNetwork"));
+        assert!(!is_mac_address("AA:BB:CC"));
+    }
+}